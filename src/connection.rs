@@ -0,0 +1,342 @@
+use std::error::Error;
+use std::fmt;
+
+/// How a neuron's outgoing axonal connections are chosen.
+#[derive(Debug, Clone, Copy)]
+pub enum ConnectionPattern {
+    /// Connect neuron `i` to neuron `(i + 1) % NUM_NEURONS`, forming a ring.
+    OneToOne,
+    /// Connect to every other neuron independently with probability `prob`.
+    Random { prob: f32 },
+    /// Connect to every neuron within `radius` (a receptive-field radius).
+    RadiusRF { radius: f32 },
+}
+
+/// Bounds for the magnitude of the weight assigned to a newly generated
+/// connection. `init` is the magnitude used by `OneToOne`; `min`/`max` bound
+/// the uniformly sampled magnitudes used by every other pattern. The sign is not part of this range: it comes from
+/// the source neuron's `NeuronType`, scaled by `PopulationSpec::inhibitory_gain`
+/// for inhibitory sources.
+#[derive(Debug, Clone, Copy)]
+pub struct RangeWeight {
+    pub min: f32,
+    pub init: f32,
+    pub max: f32,
+}
+
+/// Bounds for the per-connection axonal delay, in milliseconds.
+#[derive(Debug, Clone, Copy)]
+pub struct RangeDelay {
+    pub min: f32,
+    pub max: f32,
+}
+
+/// Describes a Brunel-style split of the population into an excitatory
+/// majority and an inhibitory minority. `excitatory_ratio` is the fraction of
+/// neurons assigned `NeuronType::Excitatory` (e.g. `0.8` for an 80/20 split);
+/// `inhibitory_gain` (`g`) scales an inhibitory connection's weight magnitude
+/// relative to an excitatory one of the same `RangeWeight`, so inhibition can
+/// be made to dominate excitation even with a smaller population.
+/// `inhibitory_density` independently scales inhibitory *connectivity*: each
+/// inhibitory source's `ConnectionPattern`-generated targets are retained with
+/// this probability (`1.0` keeps the same out-degree as an excitatory source,
+/// `< 1.0` makes inhibitory connections sparser).
+#[derive(Debug, Clone, Copy)]
+pub struct PopulationSpec {
+    pub excitatory_ratio: f32,
+    pub inhibitory_gain: f32,
+    pub inhibitory_density: f32,
+}
+
+/// Describes how `Network::new` should wire up connections: the topology
+/// (`pattern`), the weight range and the delay range, and the excitatory /
+/// inhibitory population split.
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectionSpec {
+    pub pattern: ConnectionPattern,
+    pub weight: RangeWeight,
+    pub delay: RangeDelay,
+    pub population: PopulationSpec,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionSpecError {
+    InvalidProbability(f32),
+    InvalidWeightRange { min: f32, init: f32, max: f32 },
+    InvalidRadius(f32),
+    InvalidDelayRange { min: f32, max: f32 },
+    InvalidExcitatoryRatio(f32),
+    InvalidInhibitoryGain(f32),
+    InvalidInhibitoryDensity(f32),
+    SelfConnection,
+}
+
+impl fmt::Display for ConnectionSpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionSpecError::InvalidProbability(prob) => {
+                write!(f, "connection probability {} is not in 0..=1", prob)
+            }
+            ConnectionSpecError::InvalidWeightRange { min, init, max } => write!(
+                f,
+                "weight range is invalid: expected min ({}) <= init ({}) <= max ({})",
+                min, init, max
+            ),
+            ConnectionSpecError::InvalidRadius(radius) => {
+                write!(f, "receptive field radius {} must be > 0", radius)
+            }
+            ConnectionSpecError::InvalidDelayRange { min, max } => write!(
+                f,
+                "delay range is invalid: expected min ({}) <= max ({})",
+                min, max
+            ),
+            ConnectionSpecError::InvalidExcitatoryRatio(ratio) => {
+                write!(f, "excitatory ratio {} is not in 0..=1", ratio)
+            }
+            ConnectionSpecError::InvalidInhibitoryGain(gain) => {
+                write!(f, "inhibitory gain {} must be > 0", gain)
+            }
+            ConnectionSpecError::InvalidInhibitoryDensity(density) => {
+                write!(f, "inhibitory density {} is not in 0..=1", density)
+            }
+            ConnectionSpecError::SelfConnection => {
+                write!(f, "a neuron cannot synapse onto itself")
+            }
+        }
+    }
+}
+
+impl Error for ConnectionSpecError {}
+
+impl ConnectionSpec {
+    /// Builds a `ConnectionSpec`, validating the pattern parameters and
+    /// ranges up front instead of panicking deep inside `Network::new`.
+    pub fn new(
+        pattern: ConnectionPattern,
+        weight: RangeWeight,
+        delay: RangeDelay,
+        population: PopulationSpec,
+    ) -> Result<ConnectionSpec, ConnectionSpecError> {
+        match pattern {
+            ConnectionPattern::Random { prob } => {
+                if !(0.0..=1.0).contains(&prob) {
+                    return Err(ConnectionSpecError::InvalidProbability(prob));
+                }
+            }
+            ConnectionPattern::RadiusRF { radius } => {
+                if radius <= 0.0 {
+                    return Err(ConnectionSpecError::InvalidRadius(radius));
+                }
+            }
+            ConnectionPattern::OneToOne => {}
+        }
+
+        if !(weight.min <= weight.init && weight.init <= weight.max) {
+            return Err(ConnectionSpecError::InvalidWeightRange {
+                min: weight.min,
+                init: weight.init,
+                max: weight.max,
+            });
+        }
+
+        if delay.min > delay.max {
+            return Err(ConnectionSpecError::InvalidDelayRange {
+                min: delay.min,
+                max: delay.max,
+            });
+        }
+
+        if !(0.0..=1.0).contains(&population.excitatory_ratio) {
+            return Err(ConnectionSpecError::InvalidExcitatoryRatio(
+                population.excitatory_ratio,
+            ));
+        }
+
+        if population.inhibitory_gain <= 0.0 {
+            return Err(ConnectionSpecError::InvalidInhibitoryGain(
+                population.inhibitory_gain,
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&population.inhibitory_density) {
+            return Err(ConnectionSpecError::InvalidInhibitoryDensity(
+                population.inhibitory_density,
+            ));
+        }
+
+        Ok(ConnectionSpec {
+            pattern,
+            weight,
+            delay,
+            population,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid_weight() -> RangeWeight {
+        RangeWeight {
+            min: 0.0,
+            init: 0.3,
+            max: 1.2,
+        }
+    }
+
+    fn valid_delay() -> RangeDelay {
+        RangeDelay {
+            min: 0.0,
+            max: 400.0,
+        }
+    }
+
+    fn valid_population() -> PopulationSpec {
+        PopulationSpec {
+            excitatory_ratio: 0.8,
+            inhibitory_gain: 4.0,
+            inhibitory_density: 1.0,
+        }
+    }
+
+    #[test]
+    fn accepts_valid_spec() {
+        let spec = ConnectionSpec::new(
+            ConnectionPattern::RadiusRF { radius: 0.1 },
+            valid_weight(),
+            valid_delay(),
+            valid_population(),
+        );
+        assert!(spec.is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_probability() {
+        let err = ConnectionSpec::new(
+            ConnectionPattern::Random { prob: 1.5 },
+            valid_weight(),
+            valid_delay(),
+            valid_population(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ConnectionSpecError::InvalidProbability(1.5));
+    }
+
+    #[test]
+    fn rejects_invalid_radius() {
+        let err = ConnectionSpec::new(
+            ConnectionPattern::RadiusRF { radius: 0.0 },
+            valid_weight(),
+            valid_delay(),
+            valid_population(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ConnectionSpecError::InvalidRadius(0.0));
+    }
+
+    #[test]
+    fn accepts_one_to_one_without_extra_validation() {
+        let spec = ConnectionSpec::new(
+            ConnectionPattern::OneToOne,
+            valid_weight(),
+            valid_delay(),
+            valid_population(),
+        );
+        assert!(spec.is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_weight_range() {
+        let weight = RangeWeight {
+            min: 1.0,
+            init: 0.3,
+            max: 1.2,
+        };
+        let err = ConnectionSpec::new(
+            ConnectionPattern::OneToOne,
+            weight,
+            valid_delay(),
+            valid_population(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ConnectionSpecError::InvalidWeightRange {
+                min: 1.0,
+                init: 0.3,
+                max: 1.2
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_delay_range() {
+        let delay = RangeDelay {
+            min: 400.0,
+            max: 0.0,
+        };
+        let err = ConnectionSpec::new(
+            ConnectionPattern::OneToOne,
+            valid_weight(),
+            delay,
+            valid_population(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ConnectionSpecError::InvalidDelayRange {
+                min: 400.0,
+                max: 0.0
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_excitatory_ratio() {
+        let population = PopulationSpec {
+            excitatory_ratio: 1.5,
+            ..valid_population()
+        };
+        let err = ConnectionSpec::new(
+            ConnectionPattern::OneToOne,
+            valid_weight(),
+            valid_delay(),
+            population,
+        )
+        .unwrap_err();
+        assert_eq!(err, ConnectionSpecError::InvalidExcitatoryRatio(1.5));
+    }
+
+    #[test]
+    fn rejects_invalid_inhibitory_gain() {
+        let population = PopulationSpec {
+            inhibitory_gain: 0.0,
+            ..valid_population()
+        };
+        let err = ConnectionSpec::new(
+            ConnectionPattern::OneToOne,
+            valid_weight(),
+            valid_delay(),
+            population,
+        )
+        .unwrap_err();
+        assert_eq!(err, ConnectionSpecError::InvalidInhibitoryGain(0.0));
+    }
+
+    #[test]
+    fn rejects_invalid_inhibitory_density() {
+        let population = PopulationSpec {
+            inhibitory_density: 1.5,
+            ..valid_population()
+        };
+        let err = ConnectionSpec::new(
+            ConnectionPattern::OneToOne,
+            valid_weight(),
+            valid_delay(),
+            population,
+        )
+        .unwrap_err();
+        assert_eq!(err, ConnectionSpecError::InvalidInhibitoryDensity(1.5));
+    }
+}
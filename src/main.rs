@@ -1,31 +1,108 @@
+use connection::{ConnectionPattern, ConnectionSpec, PopulationSpec, RangeDelay, RangeWeight};
 use nannou::prelude::*;
 use nannou_egui::{self, egui, Egui};
 use network::Network;
+use neuron::NeuronType;
+use recorder::RecordingFormat;
 use std::time::Duration;
 use utils::to_screen_coords;
 
+mod connection;
 mod network;
 mod neuron;
+mod recorder;
 mod utils;
 
 ///////////////////////////////////
 // Spiking Network Configuration //
 ///////////////////////////////////
 const NUM_NEURONS: usize = 800;
-const NUM_CONNECTIONS: usize = 4;
 
 const ACTION_POTENTIAL_THRESHOLD: f32 = 1.0;
-const ACTION_POTENTIAL_SPEED: f32 = 0.25;
-const MEMBRANE_DECAY_RATE: f32 = 0.3;
+// membrane time constant tau_m = R * C, governs the exponential RC decay towards v_rest
+const MEMBRANE_TAU_M: f32 = 3.3;
+// input resistance R, scales an incoming synaptic weight into a potential contribution
+const MEMBRANE_RESISTANCE: f32 = 1.0;
+const MEMBRANE_V_REST: f32 = 0.0;
+
+// use Izhikevich dynamics instead of the leaky integrate-and-fire model below
+const USE_IZHIKEVICH_MODEL: bool = false;
+// the Izhikevich model's constants are calibrated for ~1ms forward-Euler steps, so
+// update_izhikevich sub-steps at this size instead of using the raw event-driven dt
+const IZHIKEVICH_SUBSTEP_DT: f32 = 0.001;
+// bounds the number of substeps for a neuron that has been idle a long time
+const IZHIKEVICH_MAX_SUBSTEPS: u32 = 10_000;
 
 const REFRACTORY_POTENTIAL: f32 = -0.7;
 const HARD_REFRACTORY_DURATION: Duration = Duration::from_millis(250);
-const REFRACTORY_DECAY_RATE: f32 = 0.5;
 
+/// Which `ConnectionPattern` `Network::new` wires the population with; edit
+/// `CONNECTION_TOPOLOGY` below to compare patterns.
+enum ConnectionTopology {
+    OneToOne,
+    Random,
+    RadiusRF,
+}
+const CONNECTION_TOPOLOGY: ConnectionTopology = ConnectionTopology::RadiusRF;
+// connection probability used when CONNECTION_TOPOLOGY is Random
+const CONNECTION_RANDOM_PROB: f32 = 0.05;
 const MAX_CONNECTION_DISTANCE: f32 = 0.1;
-const MIN_WEIGHT_INIT: f32 = -0.3;
+// these bound the sampled weight *magnitude*; the sign comes from the source
+// neuron's excitatory/inhibitory type, see `EXCITATORY_RATIO`/`INHIBITORY_GAIN`
+const MIN_WEIGHT_INIT: f32 = 0.0;
+const WEIGHT_INIT: f32 = 0.3;
 const MAX_WEIGHT_INIT: f32 = 1.2;
-const INIT_CONNECTION_RETRIES: usize = 50;
+const MIN_CONNECTION_DELAY_MS: f32 = 0.0;
+const MAX_CONNECTION_DELAY_MS: f32 = 400.0;
+
+//////////////////////////////
+// Excitatory / Inhibitory  //
+//////////////////////////////
+// fraction of neurons assigned NeuronType::Excitatory, Brunel-style 80/20 split
+const EXCITATORY_RATIO: f32 = 0.8;
+// g: inhibitory weight magnitude relative to an excitatory connection's, g > 4
+// drives the network into the balanced inhibition-dominated regime
+const INHIBITORY_GAIN: f32 = 4.0;
+// fraction of an inhibitory source's pattern-generated targets that are kept,
+// tuning inhibitory out-degree independently of the excitatory connection count
+const INHIBITORY_DENSITY: f32 = 1.0;
+
+//////////
+// STDP //
+//////////
+const STDP_ENABLED: bool = true;
+const STDP_A_PLUS: f32 = 0.01;
+const STDP_A_MINUS: f32 = 0.012;
+const STDP_TAU_PLUS: f32 = 0.02;
+const STDP_TAU_MINUS: f32 = 0.02;
+// clamp bounds for a synapse's magnitude; which bound applies depends on the source
+// neuron's type, see `stdp_clamp_range` (excitatory: [0, MAX], inhibitory: [MIN, 0]).
+// MAX gives excitatory synapses 25% headroom above their sampled init magnitude; MIN
+// mirrors that headroom for inhibitory synapses, scaled by `INHIBITORY_GAIN` so the
+// clamp range actually contains the larger inhibitory init magnitudes (instead of
+// saturating almost every inhibitory synapse on its very first STDP event).
+const STDP_WEIGHT_MIN: f32 = -(MAX_WEIGHT_INIT * INHIBITORY_GAIN) * 1.25;
+const STDP_WEIGHT_MAX: f32 = MAX_WEIGHT_INIT * 1.25;
+// hold each neuron's incoming weight sum roughly constant to counteract runaway growth
+const STDP_NORMALIZE: bool = true;
+// skip normalizing a same-type weight group once its current sum drops below this
+// fraction of its initial sum, rather than dividing by a near-zero denominator
+const STDP_NORMALIZE_MIN_RATIO: f32 = 0.1;
+
+///////////////////////
+// Spike recording   //
+///////////////////////
+// use the compact binary encoding instead of plain CSV rows
+const SPIKE_RECORDING_BINARY: bool = false;
+const SPIKE_RECORDING_FORMAT: RecordingFormat = if SPIKE_RECORDING_BINARY {
+    RecordingFormat::Binary
+} else {
+    RecordingFormat::Csv
+};
+const SPIKE_RECORDING_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+const SPIKE_RECORDING_PATH: &str = "spikes.csv";
+// if set, stop an active recording at the first spike observed after this long
+const SPIKE_RECORDING_WINDOW: Option<Duration> = None;
 
 ////////////////////
 // Self-balancing //
@@ -66,7 +143,34 @@ fn model(app: &App) -> Model {
     let egui = Egui::from_window(&window);
 
     // initialize network
-    let net = Network::new(ASPECT_RATIO);
+    let pattern = match CONNECTION_TOPOLOGY {
+        ConnectionTopology::OneToOne => ConnectionPattern::OneToOne,
+        ConnectionTopology::Random => ConnectionPattern::Random {
+            prob: CONNECTION_RANDOM_PROB,
+        },
+        ConnectionTopology::RadiusRF => ConnectionPattern::RadiusRF {
+            radius: MAX_CONNECTION_DISTANCE,
+        },
+    };
+    let spec = ConnectionSpec::new(
+        pattern,
+        RangeWeight {
+            min: MIN_WEIGHT_INIT,
+            init: WEIGHT_INIT,
+            max: MAX_WEIGHT_INIT,
+        },
+        RangeDelay {
+            min: MIN_CONNECTION_DELAY_MS,
+            max: MAX_CONNECTION_DELAY_MS,
+        },
+        PopulationSpec {
+            excitatory_ratio: EXCITATORY_RATIO,
+            inhibitory_gain: INHIBITORY_GAIN,
+            inhibitory_density: INHIBITORY_DENSITY,
+        },
+    )
+    .expect("invalid connection spec");
+    let net = Network::new(ASPECT_RATIO, spec).expect("failed to build network");
 
     Model {
         net,
@@ -112,6 +216,7 @@ fn event(app: &App, model: &mut Model, event: Event) {
 fn update(_app: &App, model: &mut Model, _update: Update) {
     // listen for action potential events from the network
     model.net.system_receiver.try_iter().for_each(|state| {
+        model.net.recorder.observe(&state);
         let idx = state.idx;
         model.neuron_states[idx] = Some(state);
     });
@@ -140,6 +245,21 @@ fn update(_app: &App, model: &mut Model, _update: Update) {
         changed |= ui
             .add(egui::Slider::new(&mut model.std, 0.0..=2.0).text("Std"))
             .changed();
+
+        ui.separator();
+        if model.net.recorder.is_recording() {
+            if ui.button("Stop recording").clicked() {
+                model.net.recorder.stop();
+            }
+        } else if ui.button("Start recording").clicked() {
+            if let Err(err) = model
+                .net
+                .recorder
+                .start(SPIKE_RECORDING_PATH, SPIKE_RECORDING_WINDOW)
+            {
+                println!("Failed to start spike recording: {}", err);
+            }
+        }
     });
 
     // update network parameters
@@ -184,22 +304,35 @@ fn view(app: &App, model: &Model, frame: Frame) {
             }
         }
 
-        // get neuron color
+        // get neuron color, tinting the red channel for inhibitory neurons so the
+        // excitatory/inhibitory split stays visible alongside the membrane potential
+        let inhibitory_tint = match neuron.neuron_type {
+            NeuronType::Excitatory => 0,
+            NeuronType::Inhibitory => 120,
+        };
         let col = if let Some(state) = &model.neuron_states[neuron.idx] {
             if state.firing {
-                RED
+                match neuron.neuron_type {
+                    NeuronType::Excitatory => RED,
+                    NeuronType::Inhibitory => ORANGE,
+                }
             } else {
                 if !DRAW_EVERYTHING && state.membrane_potential == 0.0 {
                     continue;
                 }
+                let threshold = state.model.threshold();
+                let negative_reference = state.model.negative_reference();
                 Rgb::new(
-                    0,
-                    (state.membrane_potential.max(0.0) / ACTION_POTENTIAL_THRESHOLD * 255.0) as u8,
-                    (state.membrane_potential.min(0.0) / ACTION_POTENTIAL_THRESHOLD * -255.0) as u8,
+                    inhibitory_tint,
+                    (state.membrane_potential.max(0.0) / threshold * 255.0) as u8,
+                    (state.membrane_potential.min(0.0) / negative_reference * -255.0) as u8,
                 )
             }
         } else if DRAW_EVERYTHING {
-            BLACK
+            match neuron.neuron_type {
+                NeuronType::Excitatory => BLACK,
+                NeuronType::Inhibitory => GRAY,
+            }
         } else {
             continue;
         };
@@ -1,17 +1,57 @@
-use crate::neuron::{Neuron, NeuronState};
+use crate::connection::{ConnectionPattern, ConnectionSpec, ConnectionSpecError};
+use crate::neuron::{Neuron, NeuronState, NeuronType};
+use crate::recorder::SpikeRecorder;
+use rand::seq::SliceRandom;
 use rand::Rng;
-use rand_distr::{Distribution, WeightedIndex};
-use std::collections::HashSet;
+use rstar::{PointDistance, RTree, RTreeObject, AABB};
+use std::collections::HashMap;
 use std::sync::mpsc::{self, Receiver};
 
 pub struct Network {
     pub neurons: Vec<Neuron>,
     pub positions: Vec<(f32, f32)>,
     pub system_receiver: mpsc::Receiver<NeuronState>,
+    pub recorder: SpikeRecorder,
+}
+
+/// A neuron position indexed by `rstar` so nearby neurons can be looked up in
+/// roughly log(N) time instead of scanning a full N x N distance table.
+struct NeuronPoint {
+    idx: usize,
+    pos: [f32; 2],
+}
+
+impl RTreeObject for NeuronPoint {
+    type Envelope = AABB<[f32; 2]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        AABB::from_point(self.pos)
+    }
+}
+
+impl PointDistance for NeuronPoint {
+    fn distance_2(&self, point: &[f32; 2]) -> f32 {
+        let dx = self.pos[0] - point[0];
+        let dy = self.pos[1] - point[1];
+        dx * dx + dy * dy
+    }
+}
+
+/// Sample a value in `min..max`, or just `min` if the range is empty.
+fn sample_range(rng: &mut impl Rng, min: f32, max: f32) -> f32 {
+    if min < max {
+        rng.gen_range(min..max)
+    } else {
+        min
+    }
 }
 
 impl Network {
-    pub fn new(aspect_ratio: f32) -> Network {
+    pub fn new(aspect_ratio: f32, spec: ConnectionSpec) -> Result<Network, ConnectionSpecError> {
+        if matches!(spec.pattern, ConnectionPattern::OneToOne) && crate::NUM_NEURONS < 2 {
+            return Err(ConnectionSpecError::SelfConnection);
+        }
+
         let mut rng = rand::thread_rng();
         let (system_sender, system_receiver) = mpsc::channel();
 
@@ -30,99 +70,125 @@ impl Network {
         let axons = channels.iter().map(|(s, _)| s.clone()).collect::<Vec<_>>();
         let dendrites = channels.into_iter().map(|(_, r)| r).collect::<Vec<_>>();
 
-        // compute distance table
-        let mut distances: Vec<Vec<f32>> = Vec::new();
-        for i in 0..crate::NUM_NEURONS {
-            let mut row: Vec<f32> = Vec::new();
-            for j in 0..crate::NUM_NEURONS {
-                if i == j {
-                    row.push(std::f32::MAX);
-                    continue;
-                }
-
-                let dx = positions[i].0 - positions[j].0;
-                let dy = positions[i].1 - positions[j].1;
-                row.push((dx * dx + dy * dy).sqrt());
-            }
-            distances.push(row);
+        // assign each neuron an excitatory/inhibitory type so that `spec.population
+        // .excitatory_ratio` of the population ends up excitatory, picked uniformly
+        // at random rather than by index so type is independent of position
+        let num_excitatory =
+            (crate::NUM_NEURONS as f32 * spec.population.excitatory_ratio).round() as usize;
+        let mut shuffled_idxs: Vec<usize> = (0..crate::NUM_NEURONS).collect();
+        shuffled_idxs.shuffle(&mut rng);
+        let mut neuron_types = vec![NeuronType::Inhibitory; crate::NUM_NEURONS];
+        for &idx in shuffled_idxs.iter().take(num_excitatory) {
+            neuron_types[idx] = NeuronType::Excitatory;
         }
 
-        // initialize neurons and connections between neurons
-        let mut neurons: Vec<Neuron> = Vec::new();
-        for (neuron_idx, dendrite_handle) in dendrites.into_iter().enumerate() {
-            // map neuron distances to probability distribution
-            let weights = distances[neuron_idx]
-                .iter()
-                .map(|d| {
-                    if *d > crate::MAX_CONNECTION_DISTANCE {
-                        0.0
-                    } else {
-                        1.0 / d
-                    }
-                })
-                .collect::<Vec<f32>>();
-            let distr = WeightedIndex::new(&weights).unwrap();
-
-            // generate axonal connection indices
-            let mut tries = 0;
-            let mut target_idxs: HashSet<usize> = HashSet::new();
-            while target_idxs.len() < crate::NUM_CONNECTIONS {
-                // make sure we don't get stuck here
-                if tries > crate::INIT_CONNECTION_RETRIES {
-                    if target_idxs.len() == 0 {
-                        panic!("No connections generated for {}", neuron_idx);
-                    }
-                    println!(
-                        "Only generated {} connections for neuron {}",
-                        target_idxs.len(),
-                        neuron_idx
-                    );
-                    break;
-                }
-
-                // sample a new potential index
-                let new_idx = distr.sample(&mut rng);
-                if new_idx == neuron_idx {
-                    tries += 1;
-                    continue;
-                }
-                if !target_idxs.insert(new_idx) {
-                    tries += 1;
-                }
+        // spatial index over neuron positions, only needed for the RadiusRF pattern
+        let tree = match spec.pattern {
+            ConnectionPattern::RadiusRF { .. } => Some(RTree::bulk_load(
+                positions
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, &(x, y))| NeuronPoint { idx, pos: [x, y] })
+                    .collect::<Vec<_>>(),
+            )),
+            _ => None,
+        };
+
+        // per-neuron outgoing connections, built up for every source neuron below
+        let mut axon_handles: Vec<Vec<mpsc::Sender<usize>>> =
+            (0..crate::NUM_NEURONS).map(|_| Vec::new()).collect();
+        let mut axon_durations: Vec<Vec<std::time::Duration>> =
+            (0..crate::NUM_NEURONS).map(|_| Vec::new()).collect();
+        // per-neuron incoming weights, keyed by presynaptic source idx
+        let mut dendrite_weights: Vec<HashMap<usize, f32>> =
+            (0..crate::NUM_NEURONS).map(|_| HashMap::new()).collect();
+
+        for neuron_idx in 0..crate::NUM_NEURONS {
+            let pos = positions[neuron_idx];
+
+            let mut target_idxs: Vec<usize> = match spec.pattern {
+                ConnectionPattern::OneToOne => vec![(neuron_idx + 1) % crate::NUM_NEURONS],
+                ConnectionPattern::Random { prob } => (0..crate::NUM_NEURONS)
+                    .filter(|&target_idx| target_idx != neuron_idx && rng.gen::<f32>() < prob)
+                    .collect(),
+                ConnectionPattern::RadiusRF { radius } => tree
+                    .as_ref()
+                    .unwrap()
+                    .locate_within_distance([pos.0, pos.1], radius.powi(2))
+                    .filter(|p| p.idx != neuron_idx)
+                    .map(|p| p.idx)
+                    .collect(),
+            };
+
+            // inhibitory sources get their own connection count: thin out the
+            // pattern-generated targets by `inhibitory_density` so inhibitory
+            // out-degree can be tuned independently of the excitatory one
+            if neuron_types[neuron_idx] == NeuronType::Inhibitory {
+                target_idxs.retain(|_| rng.gen::<f32>() < spec.population.inhibitory_density);
             }
 
-            // initialize axon and dendrite containers
-            let mut axon_handles: Vec<mpsc::Sender<usize>> = Vec::new();
-            let mut axon_durations: Vec<std::time::Duration> = Vec::new();
-            let mut dendrite_weights: Vec<f32> = Vec::new();
+            if target_idxs.is_empty() {
+                println!("No connections generated for neuron {}", neuron_idx);
+            }
 
-            for target_idx in target_idxs.into_iter() {
-                axon_handles.push(axons[target_idx].clone());
-                axon_durations.push(std::time::Duration::from_millis(
-                    (distances[neuron_idx][target_idx] * 1000.0 / crate::ACTION_POTENTIAL_SPEED)
-                        as u64,
+            for target_idx in target_idxs {
+                axon_handles[neuron_idx].push(axons[target_idx].clone());
+                axon_durations[neuron_idx].push(std::time::Duration::from_millis(
+                    sample_range(&mut rng, spec.delay.min, spec.delay.max) as u64,
                 ));
 
-                // generate random weight for the connection
-                dendrite_weights
-                    .push(rng.gen_range(crate::MIN_WEIGHT_INIT..crate::MAX_WEIGHT_INIT));
+                // one-to-one connections use the fixed init magnitude, the other
+                // patterns sample one uniformly from the configured range
+                let magnitude = match spec.pattern {
+                    ConnectionPattern::OneToOne => spec.weight.init,
+                    _ => sample_range(&mut rng, spec.weight.min, spec.weight.max),
+                };
+                // the sign comes from the source neuron's type, not the sampled range:
+                // excitatory sources only ever depolarize, inhibitory sources only ever
+                // hyperpolarize, scaled by the inhibition-to-excitation ratio g
+                let weight = match neuron_types[neuron_idx] {
+                    NeuronType::Excitatory => magnitude,
+                    NeuronType::Inhibitory => -magnitude * spec.population.inhibitory_gain,
+                };
+                dendrite_weights[target_idx].insert(neuron_idx, weight);
             }
+        }
 
-            // create neuron
-            neurons.push(Neuron::new(
-                neuron_idx,
+        // initialize and start every neuron now that all connections are known
+        let mut neurons: Vec<Neuron> = Vec::new();
+        for (neuron_idx, ((dendrite_handle, weights), (handles, durations))) in dendrites
+            .into_iter()
+            .zip(dendrite_weights)
+            .zip(axon_handles.into_iter().zip(axon_durations))
+            .enumerate()
+        {
+            // the type of every presynaptic source feeding this neuron, so its thread
+            // can clamp each synapse to the sign its source's type allows
+            let source_types: HashMap<usize, NeuronType> = weights
+                .keys()
+                .map(|&src| (src, neuron_types[src]))
+                .collect();
+
+            let mut neuron = Neuron::new(neuron_idx, neuron_types[neuron_idx]);
+            neuron.start(
                 (axons[neuron_idx].clone(), dendrite_handle),
-                dendrite_weights,
-                axon_handles,
-                axon_durations,
+                weights,
+                source_types,
+                handles,
+                durations,
                 system_sender.clone(),
-            ));
+            );
+            neurons.push(neuron);
         }
 
-        Network {
+        Ok(Network {
             neurons,
             positions,
             system_receiver,
-        }
+            recorder: SpikeRecorder::new(
+                crate::SPIKE_RECORDING_FORMAT,
+                crate::SPIKE_RECORDING_FLUSH_INTERVAL,
+            ),
+        })
     }
 }
@@ -1,14 +1,139 @@
+use rand::Rng;
 use std::cmp::Reverse;
 use std::collections::{BinaryHeap, HashMap};
+use std::f32::consts::PI;
 use std::sync::mpsc;
 use std::thread;
 use std::time::{Duration, Instant};
 
+/// Clamp bounds for a synapse from `source_type`, keeping Dale's law intact:
+/// excitatory synapses stay non-negative, inhibitory synapses stay non-positive.
+fn stdp_clamp_range(source_type: NeuronType) -> (f32, f32) {
+    match source_type {
+        NeuronType::Excitatory => (0.0, crate::STDP_WEIGHT_MAX),
+        NeuronType::Inhibitory => (crate::STDP_WEIGHT_MIN, 0.0),
+    }
+}
+
+/// Sign applied to an STDP delta so it always moves a synapse's *efficacy*, not its
+/// raw value, in the intended direction: excitatory synapses strengthen by growing
+/// more positive, inhibitory synapses strengthen by growing more negative.
+fn stdp_sign(source_type: NeuronType) -> f32 {
+    match source_type {
+        NeuronType::Excitatory => 1.0,
+        NeuronType::Inhibitory => -1.0,
+    }
+}
+
+/// Potentiate a synapse after a pre-before-post spike pair, `dt` seconds apart.
+fn stdp_potentiate(
+    weights: &mut HashMap<usize, f32>,
+    source_types: &HashMap<usize, NeuronType>,
+    source_idx: usize,
+    dt: f32,
+) {
+    if let Some(w) = weights.get_mut(&source_idx) {
+        let source_type = source_types[&source_idx];
+        *w += stdp_sign(source_type) * crate::STDP_A_PLUS * (-dt / crate::STDP_TAU_PLUS).exp();
+        let (min, max) = stdp_clamp_range(source_type);
+        *w = w.clamp(min, max);
+    }
+}
+
+/// Depress a synapse after a post-before-pre spike pair, `dt` seconds apart.
+fn stdp_depress(
+    weights: &mut HashMap<usize, f32>,
+    source_types: &HashMap<usize, NeuronType>,
+    source_idx: usize,
+    dt: f32,
+) {
+    if let Some(w) = weights.get_mut(&source_idx) {
+        let source_type = source_types[&source_idx];
+        *w -= stdp_sign(source_type) * crate::STDP_A_MINUS * (-dt / crate::STDP_TAU_MINUS).exp();
+        let (min, max) = stdp_clamp_range(source_type);
+        *w = w.clamp(min, max);
+    }
+}
+
+/// Rescale the excitatory and inhibitory incoming weights independently back towards
+/// `excitatory_target_sum`/`inhibitory_target_sum`, preventing runaway growth. The two
+/// signs are normalized as separate groups, never by one global scalar: since every
+/// synapse is already clamped to its source type's sign, a single population-wide
+/// scale would blow up (or flip every sign) whenever one group's sum drifted near zero.
+fn stdp_normalize(
+    weights: &mut HashMap<usize, f32>,
+    source_types: &HashMap<usize, NeuronType>,
+    excitatory_target_sum: f32,
+    inhibitory_target_sum: f32,
+) {
+    stdp_normalize_group(
+        weights,
+        source_types,
+        NeuronType::Excitatory,
+        excitatory_target_sum,
+    );
+    stdp_normalize_group(
+        weights,
+        source_types,
+        NeuronType::Inhibitory,
+        inhibitory_target_sum,
+    );
+}
+
+/// Rescale the `group`-typed subset of `weights` back towards `target_sum`. Skipped
+/// when `target_sum` is ~zero (nothing to normalize towards) or when the current sum
+/// has drifted too close to zero relative to it (rescaling would blow up the scale).
+fn stdp_normalize_group(
+    weights: &mut HashMap<usize, f32>,
+    source_types: &HashMap<usize, NeuronType>,
+    group: NeuronType,
+    target_sum: f32,
+) {
+    if target_sum.abs() <= f32::EPSILON {
+        return;
+    }
+
+    let current_sum: f32 = weights
+        .iter()
+        .filter(|&(src, _)| source_types[src] == group)
+        .map(|(_, w)| w)
+        .sum();
+    if (current_sum / target_sum) <= crate::STDP_NORMALIZE_MIN_RATIO {
+        return;
+    }
+
+    let scale = target_sum / current_sum;
+    let (min, max) = stdp_clamp_range(group);
+    for (src, w) in weights.iter_mut() {
+        if source_types[src] == group {
+            *w = (*w * scale).clamp(min, max);
+        }
+    }
+}
+
+/// Sample from a normal distribution via the Box-Muller transform, or just
+/// `mean` if `std` is zero.
+fn sample_normal(rng: &mut impl Rng, mean: f32, std: f32) -> f32 {
+    if std <= 0.0 {
+        return mean;
+    }
+    let u1: f32 = rng.gen_range(f32::EPSILON..1.0);
+    let u2: f32 = rng.gen_range(0.0..1.0);
+    let z0 = (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos();
+    mean + std * z0
+}
+
 pub fn update_neuron(state: &mut NeuronState, incoming: Option<f32>) {
     let now = Instant::now();
     let time_delta = now - state.last_update;
 
-    if state.firing && time_delta < crate::HARD_REFRACTORY_DURATION {
+    // LIF has no refractory dynamics of its own, so it's gated by a fixed wall-clock
+    // duration; Izhikevich's own recovery variable `u` already enforces a minimum ISI
+    // via its post-spike reset (`c`/`d`), so the fixed gate would only clip that short
+    // without the model's (a, b, c, d) presets ever being allowed to vary it
+    let hard_refractory_gated = matches!(state.model, NeuronModel::LeakyIntegrateFire);
+
+    if hard_refractory_gated && state.firing && time_delta < crate::HARD_REFRACTORY_DURATION {
         // hard refractory period
         return;
     } else {
@@ -19,20 +144,22 @@ pub fn update_neuron(state: &mut NeuronState, incoming: Option<f32>) {
     // update last_update time
     state.last_update = now;
 
-    // update membrane potential
-    if state.membrane_potential > 0.0 {
-        // linear decay of the membrane potential
-        state.membrane_potential -=
-            (time_delta.as_secs_f32() * crate::MEMBRANE_DECAY_RATE).min(state.membrane_potential);
-    } else {
-        // linear decay of the refractory overshoot
-        state.membrane_potential += (time_delta.as_secs_f32() * crate::REFRACTORY_DECAY_RATE)
-            .min(-state.membrane_potential);
+    match state.model {
+        NeuronModel::LeakyIntegrateFire => update_lif(state, incoming, time_delta),
+        NeuronModel::Izhikevich(params) => update_izhikevich(state, incoming, time_delta, params),
     }
+}
+
+fn update_lif(state: &mut NeuronState, incoming: Option<f32>, time_delta: Duration) {
+    // passive RC relaxation towards rest: v <- v_rest + (v - v_rest) * exp(-dt / tau_m),
+    // exact regardless of how irregular the event-driven time_delta is
+    let decay = (-time_delta.as_secs_f32() / crate::MEMBRANE_TAU_M).exp();
+    state.membrane_potential =
+        crate::MEMBRANE_V_REST + (state.membrane_potential - crate::MEMBRANE_V_REST) * decay;
 
-    // add incoming potential
+    // add incoming potential, scaled by the membrane's input resistance
     if let Some(incoming) = incoming {
-        state.membrane_potential += incoming;
+        state.membrane_potential += crate::MEMBRANE_RESISTANCE * incoming;
 
         if state.membrane_potential >= crate::ACTION_POTENTIAL_THRESHOLD {
             // fire an action potential
@@ -42,6 +169,118 @@ pub fn update_neuron(state: &mut NeuronState, incoming: Option<f32>) {
     }
 }
 
+fn update_izhikevich(
+    state: &mut NeuronState,
+    incoming: Option<f32>,
+    time_delta: Duration,
+    params: IzhikevichParams,
+) {
+    let current = incoming.unwrap_or(0.0);
+
+    // this is event-driven, so `time_delta` can be an arbitrarily large wall-clock
+    // gap for a quiet neuron; the model's constants are calibrated for ~1ms
+    // forward-Euler steps, so sub-step at that fixed size instead of bisecting
+    // whatever interval happened to elapse
+    let mut remaining = time_delta.as_secs_f32();
+    let mut substeps = 0;
+    while remaining > 0.0 && substeps < crate::IZHIKEVICH_MAX_SUBSTEPS {
+        let dt = remaining.min(crate::IZHIKEVICH_SUBSTEP_DT);
+        // `dt` is in seconds like the rest of the event-driven model, but the
+        // model's constants are calibrated per millisecond, so convert before using
+        // them in the update below
+        let dt_ms = dt * 1_000.0;
+
+        // integrate the v update in two dt/2 substeps for numerical stability
+        for _ in 0..2 {
+            let v = state.membrane_potential;
+            let half_dt_ms = dt_ms / 2.0;
+            state.membrane_potential +=
+                half_dt_ms * (0.04 * v * v + 5.0 * v + 140.0 - state.recovery + current);
+        }
+        state.recovery += dt_ms * params.a * (params.b * state.membrane_potential - state.recovery);
+
+        if state.membrane_potential >= params.threshold {
+            // fire an action potential
+            state.firing = true;
+            state.membrane_potential = params.c;
+            state.recovery += params.d;
+            return;
+        }
+
+        remaining -= dt;
+        substeps += 1;
+    }
+}
+
+/// Izhikevich model parameters for a single neuron (mV / ms scale, see
+/// Izhikevich 2003 "Simple Model of Spiking Neurons"). Every Izhikevich neuron
+/// currently runs `IzhikevichParams::default()`; there is no per-neuron assignment
+/// yet, so distinct regular-spiking / fast-spiking / bursting presets aren't
+/// expressible in the same network until `Neuron::start` is given a way to receive
+/// a preset per neuron.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IzhikevichParams {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub threshold: f32,
+}
+
+impl Default for IzhikevichParams {
+    /// Regular-spiking cortical neuron parameters.
+    fn default() -> Self {
+        IzhikevichParams {
+            a: 0.02,
+            b: 0.2,
+            c: -65.0,
+            d: 8.0,
+            threshold: 30.0,
+        }
+    }
+}
+
+/// Selects which membrane dynamics `update_neuron` integrates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NeuronModel {
+    LeakyIntegrateFire,
+    Izhikevich(IzhikevichParams),
+}
+
+impl NeuronModel {
+    /// Membrane potential at which the neuron fires, used by the visualization
+    /// to normalize the displayed color's depolarizing (positive) half.
+    pub fn threshold(&self) -> f32 {
+        match self {
+            NeuronModel::LeakyIntegrateFire => crate::ACTION_POTENTIAL_THRESHOLD,
+            NeuronModel::Izhikevich(params) => params.threshold,
+        }
+    }
+
+    /// Magnitude used by the visualization to normalize the displayed color's
+    /// hyperpolarizing (negative) half. Izhikevich's resting potential (`c`, typically
+    /// around -65) is far from 0 relative to its `threshold` (typically 30), so reusing
+    /// `threshold` for the negative side would saturate the color across nearly the
+    /// whole subthreshold range; LIF's rest sits at 0, so `threshold` is symmetric there.
+    pub fn negative_reference(&self) -> f32 {
+        match self {
+            NeuronModel::LeakyIntegrateFire => crate::ACTION_POTENTIAL_THRESHOLD,
+            NeuronModel::Izhikevich(params) => params.c.abs(),
+        }
+    }
+
+    /// Resting `(membrane_potential, recovery)` a neuron starts at before any input.
+    /// LIF rests at `MEMBRANE_V_REST` with no recovery variable; Izhikevich's resting
+    /// point is far from 0 (`c` is typically around -65), so starting it at 0 would
+    /// let the model's `+140` constant term dominate and fire on the very first input.
+    pub fn resting(&self) -> (f32, f32) {
+        match self {
+            NeuronModel::LeakyIntegrateFire => (crate::MEMBRANE_V_REST, 0.0),
+            NeuronModel::Izhikevich(params) => (params.c, params.b * params.c),
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub struct ActionPotential {
     arrival: Instant,
@@ -53,23 +292,45 @@ pub struct NeuronState {
     pub idx: usize,
     pub firing: bool,
     pub membrane_potential: f32,
+    /// Recovery variable `u`, only meaningful for `NeuronModel::Izhikevich`.
+    pub recovery: f32,
+    pub model: NeuronModel,
     pub last_update: Instant,
     pub pending_action_potentials: BinaryHeap<Reverse<ActionPotential>>,
 }
 
+/// A neuron's neurotransmitter polarity, assigned once at construction and
+/// used to sign every weight on its outgoing connections: excitatory cells
+/// only ever depolarize their targets, inhibitory cells only ever hyperpolarize them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NeuronType {
+    Excitatory,
+    Inhibitory,
+}
+
 pub struct Neuron {
     pub idx: usize,
+    pub neuron_type: NeuronType,
     pub dendrite_idxs: Option<Vec<usize>>,
     pub dendrite: Option<mpsc::Sender<usize>>,
+    /// Pushes a new `(mean, std)` for the background noise `start` mixes into every
+    /// incoming synaptic event, letting the visualization's self-balancing logic
+    /// nudge this neuron's drive at runtime.
+    pub modifier_sender: mpsc::Sender<(f32, f32)>,
+    modifier_receiver: Option<mpsc::Receiver<(f32, f32)>>,
     pub thread: Option<thread::JoinHandle<()>>,
 }
 
 impl Neuron {
-    pub fn new(idx: usize) -> Neuron {
+    pub fn new(idx: usize, neuron_type: NeuronType) -> Neuron {
+        let (modifier_sender, modifier_receiver) = mpsc::channel();
         Neuron {
             idx,
+            neuron_type,
             dendrite_idxs: None,
             dendrite: None,
+            modifier_sender,
+            modifier_receiver: Some(modifier_receiver),
             thread: None,
         }
     }
@@ -77,7 +338,8 @@ impl Neuron {
     pub fn start(
         &mut self,
         dendrite_handles: (mpsc::Sender<usize>, mpsc::Receiver<usize>),
-        dendrite_weights: HashMap<usize, f32>,
+        mut dendrite_weights: HashMap<usize, f32>,
+        source_types: HashMap<usize, NeuronType>,
         axon_handles: Vec<mpsc::Sender<usize>>,
         axon_durations: Vec<Duration>,
         system_handle: mpsc::Sender<NeuronState>,
@@ -85,18 +347,56 @@ impl Neuron {
         self.dendrite = Some(dendrite_handles.0.clone());
         self.dendrite_idxs = Some(dendrite_weights.keys().copied().collect());
 
+        let modifier_receiver = self.modifier_receiver.take().unwrap();
+
+        // STDP normalizes each type's incoming weights back towards its own initial
+        // sum, see `stdp_normalize`
+        let initial_excitatory_sum: f32 = dendrite_weights
+            .iter()
+            .filter(|&(src, _)| source_types[src] == NeuronType::Excitatory)
+            .map(|(_, w)| w)
+            .sum();
+        let initial_inhibitory_sum: f32 = dendrite_weights
+            .iter()
+            .filter(|&(src, _)| source_types[src] == NeuronType::Inhibitory)
+            .map(|(_, w)| w)
+            .sum();
+
         let idx = self.idx;
         self.thread = Some(thread::spawn(move || {
-            // initialize neuron state
+            let mut rng = rand::thread_rng();
+            // background noise (mean, std) mixed into every incoming synaptic event,
+            // updated from the visualization's self-balancing logic via modifier_sender
+            let mut background = (0.0f32, 0.0f32);
+
+            // per-presynaptic-source arrival time and own last spike time, used by STDP
+            let mut last_arrival: HashMap<usize, Instant> = HashMap::new();
+            let mut last_fire: Option<Instant> = None;
+
+            // initialize neuron state at its model's own resting point
+            let model = if crate::USE_IZHIKEVICH_MODEL {
+                NeuronModel::Izhikevich(IzhikevichParams::default())
+            } else {
+                NeuronModel::LeakyIntegrateFire
+            };
+            let (membrane_potential, recovery) = model.resting();
             let mut state = NeuronState {
                 idx: idx,
                 firing: false,
-                membrane_potential: 0.0,
+                membrane_potential,
+                recovery,
+                model,
                 last_update: Instant::now() - crate::HARD_REFRACTORY_DURATION,
                 pending_action_potentials: BinaryHeap::new(),
             };
 
             loop {
+                // pick up the latest (mean, std) without blocking; it's only consulted
+                // below, so this is fine to update lazily between dendrite events
+                while let Ok(update) = modifier_receiver.try_recv() {
+                    background = update;
+                }
+
                 // wait until we receive an action potential or a pending one arrives
                 match dendrite_handles.1.recv_timeout(
                     state
@@ -106,21 +406,64 @@ impl Neuron {
                             ap.0.arrival - Instant::now()
                         }),
                 ) {
-                    Ok(target_idx) => {
+                    Ok(source_idx) => {
                         /////////////////////////////////////////////////////
                         // we received an action potential (other -> self) //
                         /////////////////////////////////////////////////////
+                        let now = Instant::now();
 
-                        // get the weight of the incoming signal, simply fire if no weight is set
+                        if crate::STDP_ENABLED {
+                            // post-before-pre: we already fired and this presynaptic spike
+                            // arrives shortly after, so depress the synapse
+                            if let Some(last_fire) = last_fire {
+                                if now > last_fire {
+                                    stdp_depress(
+                                        &mut dendrite_weights,
+                                        &source_types,
+                                        source_idx,
+                                        (now - last_fire).as_secs_f32(),
+                                    );
+                                }
+                            }
+                            last_arrival.insert(source_idx, now);
+                        }
+
+                        // get the weight of the incoming signal, simply fire if no weight is set,
+                        // and mix in the current background noise
                         let weight = dendrite_weights
-                            .get(&target_idx)
+                            .get(&source_idx)
                             .copied()
-                            .unwrap_or(crate::ACTION_POTENTIAL_THRESHOLD);
+                            .unwrap_or(crate::ACTION_POTENTIAL_THRESHOLD)
+                            + sample_normal(&mut rng, background.0, background.1);
                         // update own state with the incoming signal
                         update_neuron(&mut state, Some(weight));
 
                         // check if we are firing
                         if state.firing {
+                            if crate::STDP_ENABLED {
+                                // pre-before-post: potentiate every synapse whose presynaptic
+                                // spike preceded this postsynaptic spike
+                                for (&src, &arrival) in last_arrival.iter() {
+                                    if now > arrival {
+                                        stdp_potentiate(
+                                            &mut dendrite_weights,
+                                            &source_types,
+                                            src,
+                                            (now - arrival).as_secs_f32(),
+                                        );
+                                    }
+                                }
+                                if crate::STDP_NORMALIZE {
+                                    stdp_normalize(
+                                        &mut dendrite_weights,
+                                        &source_types,
+                                        initial_excitatory_sum,
+                                        initial_inhibitory_sum,
+                                    );
+                                }
+                                last_fire = Some(now);
+                            }
+
                             // schedule action potentials for all axonal connections
                             for i in 0..axon_handles.len() {
                                 let ap = ActionPotential {
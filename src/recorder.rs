@@ -0,0 +1,213 @@
+use crate::neuron::NeuronState;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// On-disk layout for a recording session: plain CSV rows, or a compact
+/// binary encoding (little-endian `u32` neuron index, `u64` timestamp_ms).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordingFormat {
+    Csv,
+    Binary,
+}
+
+/// Logs firing events crossing the network's system channel to disk so a
+/// spike raster and per-neuron firing rates can be reconstructed offline.
+pub struct SpikeRecorder {
+    format: RecordingFormat,
+    flush_interval: Duration,
+    writer: Option<BufWriter<File>>,
+    start_time: Option<Instant>,
+    window: Option<Duration>,
+    last_flush: Instant,
+}
+
+impl SpikeRecorder {
+    pub fn new(format: RecordingFormat, flush_interval: Duration) -> SpikeRecorder {
+        SpikeRecorder {
+            format,
+            flush_interval,
+            writer: None,
+            start_time: None,
+            window: None,
+            last_flush: Instant::now(),
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    /// Begins recording to `path`, truncating any existing file. `window`,
+    /// if set, stops the recording at the first firing state observed after
+    /// that much time has elapsed; there is no timer that stops it earlier.
+    pub fn start(&mut self, path: impl AsRef<Path>, window: Option<Duration>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        if self.format == RecordingFormat::Csv {
+            writeln!(writer, "neuron_idx,timestamp_ms")?;
+        }
+
+        self.writer = Some(writer);
+        self.start_time = Some(Instant::now());
+        self.window = window;
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(mut writer) = self.writer.take() {
+            let _ = writer.flush();
+        }
+        self.start_time = None;
+    }
+
+    /// Records `state` if it is firing and a recording is active, flushing
+    /// periodically and auto-stopping if the recording window has elapsed.
+    pub fn observe(&mut self, state: &NeuronState) {
+        if !state.firing {
+            return;
+        }
+
+        let start_time = match self.start_time {
+            Some(start_time) => start_time,
+            None => return,
+        };
+
+        if let Some(window) = self.window {
+            if start_time.elapsed() >= window {
+                self.stop();
+                return;
+            }
+        }
+
+        let timestamp_ms = start_time.elapsed().as_millis() as u64;
+        if let Some(writer) = self.writer.as_mut() {
+            let result = match self.format {
+                RecordingFormat::Csv => writeln!(writer, "{},{}", state.idx, timestamp_ms),
+                RecordingFormat::Binary => writer
+                    .write_all(&(state.idx as u32).to_le_bytes())
+                    .and_then(|_| writer.write_all(&timestamp_ms.to_le_bytes())),
+            };
+            if let Err(err) = result {
+                println!("Failed to record spike for neuron {}: {}", state.idx, err);
+            }
+        }
+
+        if self.last_flush.elapsed() >= self.flush_interval {
+            if let Some(writer) = self.writer.as_mut() {
+                let _ = writer.flush();
+            }
+            self.last_flush = Instant::now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::neuron::NeuronModel;
+    use std::collections::BinaryHeap;
+
+    fn spike_state(idx: usize, firing: bool) -> NeuronState {
+        NeuronState {
+            idx,
+            firing,
+            membrane_potential: 0.0,
+            recovery: 0.0,
+            model: NeuronModel::LeakyIntegrateFire,
+            last_update: Instant::now(),
+            pending_action_potentials: BinaryHeap::new(),
+        }
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "spiking-network-test-{}-{}-{}",
+            std::process::id(),
+            name,
+            rand::random::<u64>()
+        ))
+    }
+
+    #[test]
+    fn not_recording_until_started() {
+        let recorder = SpikeRecorder::new(RecordingFormat::Csv, Duration::from_secs(1));
+        assert!(!recorder.is_recording());
+    }
+
+    #[test]
+    fn start_and_stop_toggle_is_recording() {
+        let path = temp_path("start-stop");
+        let mut recorder = SpikeRecorder::new(RecordingFormat::Csv, Duration::from_secs(1));
+
+        recorder.start(&path, None).unwrap();
+        assert!(recorder.is_recording());
+
+        recorder.stop();
+        assert!(!recorder.is_recording());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn observe_ignores_non_firing_states() {
+        let path = temp_path("ignore-non-firing");
+        let mut recorder = SpikeRecorder::new(RecordingFormat::Csv, Duration::from_secs(1));
+        recorder.start(&path, None).unwrap();
+        recorder.observe(&spike_state(0, false));
+        recorder.stop();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents, "neuron_idx,timestamp_ms\n");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn observe_writes_csv_row_for_firing_state() {
+        let path = temp_path("csv-row");
+        let mut recorder = SpikeRecorder::new(RecordingFormat::Csv, Duration::from_secs(1));
+        recorder.start(&path, None).unwrap();
+        recorder.observe(&spike_state(3, true));
+        recorder.stop();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let mut lines = contents.lines();
+        assert_eq!(lines.next(), Some("neuron_idx,timestamp_ms"));
+        let row = lines.next().unwrap();
+        assert!(row.starts_with("3,"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn observe_writes_binary_row_for_firing_state() {
+        let path = temp_path("binary-row");
+        let mut recorder = SpikeRecorder::new(RecordingFormat::Binary, Duration::from_secs(1));
+        recorder.start(&path, None).unwrap();
+        recorder.observe(&spike_state(7, true));
+        recorder.stop();
+
+        let contents = std::fs::read(&path).unwrap();
+        assert_eq!(contents.len(), 12);
+        assert_eq!(u32::from_le_bytes(contents[0..4].try_into().unwrap()), 7);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn observe_auto_stops_once_window_elapses() {
+        let path = temp_path("window");
+        let mut recorder = SpikeRecorder::new(RecordingFormat::Csv, Duration::from_secs(1));
+        recorder
+            .start(&path, Some(Duration::from_millis(0)))
+            .unwrap();
+
+        std::thread::sleep(Duration::from_millis(1));
+        recorder.observe(&spike_state(0, true));
+        assert!(!recorder.is_recording());
+
+        let _ = std::fs::remove_file(&path);
+    }
+}